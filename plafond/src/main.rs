@@ -1,5 +1,8 @@
-use std::{collections::{HashMap, HashSet}, fs, io::{BufRead, BufReader}, path::PathBuf, process};
+use std::{collections::{HashMap, HashSet}, fs, io::Read, os::unix::io::{AsRawFd, FromRawFd, RawFd}, path::PathBuf, process};
 use anyhow::{Result, Context, anyhow};
+use nix::dir::Dir;
+use nix::fcntl::{openat, AtFlags, OFlag};
+use nix::sys::stat::{fstatat, Mode, SFlag};
 use nix::unistd::{User, Uid};
 use clap::{ArgGroup, CommandFactory, Parser};
 
@@ -24,6 +27,269 @@ struct Cli {
     ptree: Option<u32>,
 }
 
+/// A stable handle onto `/proc/{pid}`, opened once up front.
+///
+/// Every subsequent read goes through this fd via `openat` instead of
+/// re-deriving `/proc/{pid}/...` paths, so a PID reused by the kernel
+/// mid-traversal can't cause us to silently read a different process.
+/// Once the process exits, the kernel tears down its `/proc` entries and
+/// reads through this handle fail with `ESRCH`/`ENOENT`, which we surface
+/// as [`PidFdError::Vanished`] instead of quietly returning empty data.
+struct PidFd {
+    pid: u32,
+    fd: RawFd,
+}
+
+#[derive(Debug)]
+enum PidFdError {
+    /// The process exited (and its data became unreachable) partway
+    /// through inspection.
+    Vanished(u32),
+    Io(std::io::Error),
+}
+
+impl std::fmt::Display for PidFdError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PidFdError::Vanished(pid) => write!(f, "process {pid} vanished during inspection"),
+            PidFdError::Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for PidFdError {}
+
+/// Maps an errno from a `/proc/{pid}/...` syscall to the right
+/// `PidFdError` variant. Only `ENOENT`/`ESRCH` -- the process having
+/// exited -- count as "vanished"; anything else (most notably `EACCES`,
+/// a live process we simply lack privilege to read) is a plain I/O
+/// failure and must not be misreported as the process being gone.
+fn classify_proc_error(pid: u32, err: nix::errno::Errno) -> PidFdError {
+    match err {
+        nix::errno::Errno::ENOENT | nix::errno::Errno::ESRCH => PidFdError::Vanished(pid),
+        other => PidFdError::Io(std::io::Error::from_raw_os_error(other as i32)),
+    }
+}
+
+impl PidFd {
+    /// Opens `/proc/{pid}` once as a directory fd. All later reads for
+    /// this process should go through the returned handle rather than
+    /// re-opening `/proc/{pid}/...` by path.
+    pub fn open(pid: u32) -> Result<PidFd> {
+        let path = format!("/proc/{pid}");
+        let fd = nix::fcntl::open(path.as_str(), OFlag::O_DIRECTORY | OFlag::O_CLOEXEC, Mode::empty())
+            .map_err(|e| classify_proc_error(pid, e))?;
+        Ok(PidFd { pid, fd })
+    }
+
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// Reads the full contents of a file named relative to this process's
+    /// proc directory (e.g. `"comm"`, `"cmdline"`, `"limits"`, `"status"`).
+    pub fn read_relative(&self, name: &str) -> Result<String, PidFdError> {
+        let raw = openat(self.fd, name, OFlag::O_RDONLY, Mode::empty())
+            .map_err(|e| classify_proc_error(self.pid, e))?;
+        let mut file = unsafe { fs::File::from_raw_fd(raw) };
+        let mut buf = String::new();
+        file.read_to_string(&mut buf).map_err(PidFdError::Io)?;
+        Ok(buf)
+    }
+
+    /// Opens `/proc/{pid}/task/{tid}` relative to this process's already-open
+    /// proc directory, so a thread that exits mid-enumeration is reported as
+    /// `PidFdError::Vanished` rather than misattributed.
+    pub fn open_task(&self, tid: u32) -> Result<PidFd, PidFdError> {
+        let name = format!("task/{tid}");
+        let raw = openat(self.fd, name.as_str(), OFlag::O_DIRECTORY | OFlag::O_CLOEXEC, Mode::empty())
+            .map_err(|e| classify_proc_error(tid, e))?;
+        Ok(PidFd { pid: tid, fd: raw })
+    }
+
+    /// Opens `fd/` (or any other subdirectory, e.g. `task/`) relative to
+    /// this process's proc directory, for callers that need to enumerate
+    /// entries rather than read a single file. The returned `Dir` owns its
+    /// fd, so iterating it never re-derives a `/proc/{pid}/...` path.
+    pub fn open_dir_relative(&self, name: &str) -> Result<Dir, PidFdError> {
+        let raw = openat(
+            self.fd,
+            name,
+            OFlag::O_RDONLY | OFlag::O_DIRECTORY,
+            Mode::empty(),
+        )
+        .map_err(|e| classify_proc_error(self.pid, e))?;
+        Dir::from_fd(raw).map_err(|e| classify_proc_error(self.pid, e))
+    }
+}
+
+impl Drop for PidFd {
+    fn drop(&mut self) {
+        let _ = nix::unistd::close(self.fd);
+    }
+}
+
+/// Process state, mirroring the single-character codes in field 3 of
+/// `/proc/{pid}/stat`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProcessStatus {
+    Run,
+    Sleep,
+    UninterruptibleDiskSleep,
+    Idle,
+    Zombie,
+    Stop,
+    Tracing,
+    Dead,
+    Paging,
+    Wakekill,
+    Parked,
+    Unknown(char),
+}
+
+impl From<char> for ProcessStatus {
+    fn from(c: char) -> Self {
+        match c {
+            'R' => ProcessStatus::Run,
+            'S' => ProcessStatus::Sleep,
+            'D' => ProcessStatus::UninterruptibleDiskSleep,
+            'I' => ProcessStatus::Idle,
+            'Z' => ProcessStatus::Zombie,
+            'T' => ProcessStatus::Stop,
+            't' => ProcessStatus::Tracing,
+            'X' | 'x' => ProcessStatus::Dead,
+            'W' => ProcessStatus::Paging,
+            'K' => ProcessStatus::Wakekill,
+            'P' => ProcessStatus::Parked,
+            other => ProcessStatus::Unknown(other),
+        }
+    }
+}
+
+/// cgroup v2 ceilings for the controllers that matter to a limits
+/// inspector. `*_max` is `u64::MAX` when the kernel reports the literal
+/// `"max"` (i.e. no ceiling set on that controller), mirroring how
+/// `parse_limit` treats rlimit's `"unlimited"`.
+struct CgroupLimits {
+    mem_current: u64,
+    mem_max: u64,
+    pids_current: u64,
+    pids_max: u64,
+    swap_current: u64,
+    swap_max: u64,
+}
+
+/// A breakdown of `/proc/{pid}/fd/*` by what each fd actually points at,
+/// so "4000 fds open against a limit of 4096" becomes actionable (a
+/// socket leak vs. a file-handle leak). `inaccessible` counts entries we
+/// couldn't `readlink`/`stat`, e.g. a fd that closed mid-enumeration,
+/// rather than aborting the whole count.
+#[derive(Debug, Default, Clone, Copy)]
+struct FdBreakdown {
+    regular: u64,
+    directory: u64,
+    socket: u64,
+    pipe: u64,
+    anon_inode: u64,
+    device: u64,
+    inaccessible: u64,
+    total: u64,
+}
+
+impl FdBreakdown {
+    fn bump(&mut self, category: FdCategory) {
+        match category {
+            FdCategory::Regular => self.regular += 1,
+            FdCategory::Directory => self.directory += 1,
+            FdCategory::Socket => self.socket += 1,
+            FdCategory::Pipe => self.pipe += 1,
+            FdCategory::AnonInode => self.anon_inode += 1,
+            FdCategory::Device => self.device += 1,
+        }
+    }
+}
+
+/// What an open fd's target actually is, independent of how we found out
+/// (readlink target string vs. a stat'd mode) -- kept as a pure enum so
+/// the classification logic can be unit tested without real fds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FdCategory {
+    Regular,
+    Directory,
+    Socket,
+    Pipe,
+    AnonInode,
+    Device,
+}
+
+/// Recognizes the synthetic `readlink` targets procfs uses for fds that
+/// aren't backed by a real path: `socket:[inode]`, `pipe:[inode]`, and
+/// `anon_inode:...` (epoll, eventfd, etc). Returns `None` for a real path
+/// (regular file, directory, or device node), which the caller must then
+/// `stat` to tell apart.
+fn classify_fd_target(target: &str) -> Option<FdCategory> {
+    if target.starts_with("socket:[") {
+        Some(FdCategory::Socket)
+    } else if target.starts_with("pipe:[") {
+        Some(FdCategory::Pipe)
+    } else if target.starts_with("anon_inode:") {
+        Some(FdCategory::AnonInode)
+    } else {
+        None
+    }
+}
+
+/// Classifies a real path's `st_mode` into regular/directory/device.
+/// `S_IFMT` is a multi-bit field, not independent flags (e.g.
+/// `S_IFBLK & S_IFDIR == S_IFDIR`), so mask it out and compare for
+/// equality rather than `SFlag::contains`.
+fn classify_fd_mode(st_mode: u32) -> FdCategory {
+    match st_mode & SFlag::S_IFMT.bits() {
+        m if m == SFlag::S_IFDIR.bits() => FdCategory::Directory,
+        m if m == SFlag::S_IFCHR.bits() || m == SFlag::S_IFBLK.bits() => FdCategory::Device,
+        _ => FdCategory::Regular,
+    }
+}
+
+/// A kernel thread's `/proc/{tid}/stat` has `PF_KTHREAD` set in its flags
+/// field (field 9); `kthreadd` (PID 2) is the kernel's own reaper for
+/// them, so a thread with an empty `cmdline` parented by PID 2 is the
+/// fallback signal on kernels/containers where the flags bit isn't
+/// reliably visible.
+const PF_KTHREAD: u64 = 0x0020_0000;
+const KTHREADD_PID: u32 = 2;
+
+/// A single thread within an inspected process, read from
+/// `/proc/{pid}/task/{tid}`. Lighter than `ProcStats` since a thread has
+/// no separate fd table, rlimits, or cgroup membership of its own -- those
+/// are process-wide -- but it has its own scheduling state and CPU time,
+/// which is exactly what you need to spot the one thread that's spinning
+/// or stuck in `D` state inside an otherwise-healthy process.
+struct ThreadStats {
+    tid: u32,
+    name: String,
+    status: ProcessStatus,
+    utime_secs: f64,
+    stime_secs: f64,
+    is_kernel_thread: bool,
+}
+
+/// A process's namespace membership, identified by each namespace's inode
+/// number (the `4026531840` in `net:[4026531840]`). Two processes sharing
+/// an inode for a given namespace type are in that same namespace -- this
+/// is how containerized processes get clustered apart from the host's
+/// init-namespace ones.
+#[derive(Debug, Default, Clone, Copy)]
+struct NamespaceIds {
+    pid: Option<u64>,
+    mnt: Option<u64>,
+    net: Option<u64>,
+    user: Option<u64>,
+    cgroup: Option<u64>,
+    uts: Option<u64>,
+    ipc: Option<u64>,
+}
+
 struct ProcStats {
     // Control data
     pid: u32,
@@ -32,6 +298,7 @@ struct ProcStats {
 
     // Open file data
     open_fds: u64,
+    fd_breakdown: FdBreakdown,
     fd_soft_limit: u64,
     fd_hard_limit: u64,
 
@@ -47,48 +314,86 @@ struct ProcStats {
     threads_soft_limit: Option<u64>,
     threads_hard_limit: Option<u64>,
 
+    // Scheduling / CPU data (from /proc/pid/stat)
+    status: ProcessStatus,
+    utime_secs: f64,
+    stime_secs: f64,
+    num_threads: u32,
+    priority: i64,
+    nice: i64,
+    starttime_ticks: u64,
+
     // RLimits
-    rlimits: HashMap<String, (u64, u64)>
+    rlimits: HashMap<String, (u64, u64)>,
+
+    // cgroup v2 ceilings, when the host/container is on a v2 hierarchy
+    cgroup_limits: Option<CgroupLimits>,
+
+    // Namespace membership, for clustering by container boundary
+    namespaces: NamespaceIds,
 }
 
 impl ProcStats {
-    pub fn gather(pid: u32) -> Result<ProcStats> {
-        let pid_path = PathBuf::from(format!("/proc/{pid}"));
+    /// Gathers stats for the process behind `pidfd`. Every `/proc` field is
+    /// read through the same open handle, so they all describe one
+    /// consistent snapshot of the process even if its PID is recycled the
+    /// moment after this call returns. The cgroup v2 controller files are
+    /// the exception: they are read by path under `/sys/fs/cgroup`.
+    pub fn gather(pidfd: &PidFd) -> Result<ProcStats> {
+        let pid = pidfd.pid();
 
-        let name = match Self::read_name(&pid_path) {
+        let name = match Self::read_name(pidfd) {
             Ok(n) => n,
             Err(e) => return Err(anyhow!("Failed to read process name: {e}")),
         };
 
-        let cmd = match Self::read_cmdline(&pid_path) {
+        let cmd = match Self::read_cmdline(pidfd) {
             Ok(c) => c,
             Err(e) => return Err(anyhow!("Failed to read cmdline: {e}")),
         };
 
-        let open_fds = match Self::count_open_fds(&pid_path) {
-            Ok(n) => n,
-            Err(_) => 0, // Default to 0 if inaccessible
+        let fd_breakdown = match Self::count_open_fds(pidfd) {
+            Ok(b) => b,
+            Err(_) => FdBreakdown::default(), // Default to empty if inaccessible
         };
+        let open_fds = fd_breakdown.total;
 
         let (
             rlimits, fd_limits, mem_limits, thread_limits
-        ) = match Self::parse_limits(&pid_path) {
+        ) = match Self::parse_limits(pidfd) {
             Ok(v) => v,
             Err(e) => return Err(anyhow!("Failed to parse limits: {e}")),
         };
 
         let (
             vm_rss, vm_size, vm_locked, threads
-        ) = match Self::parse_status(&pid_path) {
+        ) = match Self::parse_status(pidfd) {
             Ok(v) => v,
             Err(e) => return Err(anyhow!("Failed to parse status: {e}")),
         };
 
+        let (
+            status, utime_secs, stime_secs, num_threads, priority, nice, starttime_ticks
+        ) = match Self::parse_stat(pidfd) {
+            Ok(v) => v,
+            Err(e) => return Err(anyhow!("Failed to parse stat: {e}")),
+        };
+
+        // cgroup v2 may simply not be present (cgroup v1-only host, or a
+        // sandbox without /sys/fs/cgroup mounted) -- degrade to
+        // rlimits-only rather than failing the whole gather.
+        let cgroup_limits = Self::parse_cgroup_limits(pidfd).unwrap_or(None);
+
+        // `ns/` entries can be unreadable without sufficient privilege;
+        // degrade to "unknown namespace" rather than failing gather.
+        let namespaces = Self::parse_namespaces(pidfd).unwrap_or_default();
+
         Ok(ProcStats {
             pid,
             name,
             cmd,
             open_fds,
+            fd_breakdown,
             fd_soft_limit: fd_limits.0,
             fd_hard_limit: fd_limits.1,
             mem_soft_limit: Some(mem_limits.0),
@@ -96,33 +401,84 @@ impl ProcStats {
             threads,
             threads_soft_limit: Some(thread_limits.0),
             threads_hard_limit: Some(thread_limits.1),
+            status,
+            utime_secs,
+            stime_secs,
+            num_threads,
+            priority,
+            nice,
+            starttime_ticks,
             vm_rss,
             vm_size,
             vm_locked,
             rlimits,
+            cgroup_limits,
+            namespaces,
         })
     }
 
-    pub fn read_name(pid_path: &PathBuf) -> Result<String> {
-        match fs::read_to_string(pid_path.join("comm")) {
-            Ok(s) => Ok(s.trim().to_string()),
-            Err(e) => Err(anyhow!(e))
-        }
+    pub fn read_name(pidfd: &PidFd) -> Result<String> {
+        Ok(pidfd.read_relative("comm")?.trim().to_string())
     }
 
-    pub fn read_cmdline(pid_path: &PathBuf) -> Result<String> {
-        Ok(todo!())
+    /// `/proc/{pid}/cmdline` is NUL-separated (and NUL-terminated), not
+    /// whitespace-separated, so join on spaces for a human-readable line.
+    pub fn read_cmdline(pidfd: &PidFd) -> Result<String> {
+        let raw = pidfd.read_relative("cmdline")?;
+        Ok(raw
+            .split('\0')
+            .filter(|arg| !arg.is_empty())
+            .collect::<Vec<_>>()
+            .join(" "))
     }
 
-    pub fn count_open_fds(pid_path: &PathBuf) -> Result<u64> {
-        match fs::read_dir(pid_path.join("fd")) {
-            Ok(dir) => Ok(dir.count() as u64),
-            Err(e) => Err(anyhow!(e))
+    /// Categorizes every entry in `/proc/{pid}/fd/` by readlinking its
+    /// target. Sockets, pipes, and anon inodes are identifiable by their
+    /// synthetic `readlink` target (`socket:[inode]`, `pipe:[inode]`,
+    /// `anon_inode:...`); everything else is stat'd to tell regular files,
+    /// directories, and character/block devices apart.
+    pub fn count_open_fds(pidfd: &PidFd) -> Result<FdBreakdown> {
+        let mut dir = pidfd.open_dir_relative("fd")?;
+        let dir_fd = dir.as_raw_fd();
+        let mut breakdown = FdBreakdown::default();
+
+        for entry in dir.iter() {
+            let entry = match entry {
+                Ok(e) => e,
+                Err(_) => {
+                    breakdown.inaccessible += 1;
+                    continue;
+                }
+            };
+            let name = entry.file_name();
+            if name.to_bytes() == b"." || name.to_bytes() == b".." {
+                continue;
+            }
+            breakdown.total += 1;
+
+            let target = match nix::fcntl::readlinkat(dir_fd, name) {
+                Ok(t) => t,
+                Err(_) => {
+                    breakdown.inaccessible += 1;
+                    continue;
+                }
+            };
+            let target = target.to_string_lossy();
+
+            match classify_fd_target(&target) {
+                Some(category) => breakdown.bump(category),
+                None => match fstatat(dir_fd, name, AtFlags::empty()) {
+                    Ok(stat) => breakdown.bump(classify_fd_mode(stat.st_mode)),
+                    Err(_) => breakdown.inaccessible += 1,
+                },
+            }
         }
+
+        Ok(breakdown)
     }
 
     pub fn parse_limits(
-        pid_path: &PathBuf
+        pidfd: &PidFd
     ) -> Result<(
         HashMap<String, (u64, u64)>,
         (u64, u64),
@@ -134,30 +490,23 @@ impl ProcStats {
         let mut mem_limits = (0,0);
         let mut thread_limits = (0,0);
 
-        let file = match fs::File::open(pid_path.join("limits")) {
-            Ok(f) => f,
-            Err(e) => return Err(anyhow!(e))
-        };
-
-        let reader = BufReader::new(file);
-
-        for line in reader.lines().skip(1) {
-            if let Ok(l) = line {
-                let parts: Vec<_> = l.split_whitespace().collect();
-                if parts.len() >= 4 {
-                    let name = parts[..parts.len() - 3].join(" ");
-                    let soft = parse_limit(parts[parts.len() - 3]);
-                    let hard = parse_limit(parts[parts.len() - 2]);
+        let contents = pidfd.read_relative("limits")?;
 
-                    match name.as_str() {
-                        "Max open files" => fd_limits = (soft, hard),
-                        "Max address space" => mem_limits = (soft, hard),
-                        "Max processes" => thread_limits = (soft, hard),
-                        _ => {}
-                    }
+        for line in contents.lines().skip(1) {
+            let parts: Vec<_> = line.split_whitespace().collect();
+            if parts.len() >= 4 {
+                let name = parts[..parts.len() - 3].join(" ");
+                let soft = parse_limit(parts[parts.len() - 3]);
+                let hard = parse_limit(parts[parts.len() - 2]);
 
-                    rlimits.insert(name, (soft, hard));
+                match name.as_str() {
+                    "Max open files" => fd_limits = (soft, hard),
+                    "Max address space" => mem_limits = (soft, hard),
+                    "Max processes" => thread_limits = (soft, hard),
+                    _ => {}
                 }
+
+                rlimits.insert(name, (soft, hard));
             }
         }
 
@@ -165,37 +514,228 @@ impl ProcStats {
         Ok((rlimits, fd_limits, mem_limits, thread_limits))
     }
 
-    pub fn parse_status(pid_path: &PathBuf) -> Result<(u64, u64, u64, u32)> {
-        let file = match fs::File::open(pid_path.join("status")) {
-            Ok(f) => f,
-            Err(e) => return Err(anyhow!(e)),
-        };
-    
+    pub fn parse_status(pidfd: &PidFd) -> Result<(u64, u64, u64, u32)> {
+        let contents = pidfd.read_relative("status")?;
+
         let mut vm_rss = 0;
         let mut vm_size = 0;
         let mut vm_locked = 0;
         let mut threads = 0;
-    
-        for line in BufReader::new(file).lines() {
-            if let Ok(l) = line {
-                if l.starts_with("VmRSS:") {
-                    vm_rss = extract_kb(&l);
-                } else if l.starts_with("VmSize:") {
-                    vm_size = extract_kb(&l);
-                } else if l.starts_with("VmLck:") {
-                    vm_locked = extract_kb(&l);
-                } else if l.starts_with("Threads:") {
-                    threads = l.split_whitespace()
-                        .nth(1)
-                        .unwrap_or("0")
-                        .parse()
-                        .unwrap_or(0);
-                }
+
+        for l in contents.lines() {
+            if l.starts_with("VmRSS:") {
+                vm_rss = extract_kb(l);
+            } else if l.starts_with("VmSize:") {
+                vm_size = extract_kb(l);
+            } else if l.starts_with("VmLck:") {
+                vm_locked = extract_kb(l);
+            } else if l.starts_with("Threads:") {
+                threads = l.split_whitespace()
+                    .nth(1)
+                    .unwrap_or("0")
+                    .parse()
+                    .unwrap_or(0);
             }
         }
-    
+
         Ok((vm_rss, vm_size, vm_locked, threads))
     }
+
+    /// Parses `/proc/{pid}/stat`. The whole file is a single line, but
+    /// `comm` (field 2) is wrapped in parens and may itself contain spaces
+    /// or parens, so we locate it by splitting on the *last* `)` rather
+    /// than naively tokenizing on whitespace.
+    pub fn parse_stat(pidfd: &PidFd) -> Result<(ProcessStatus, f64, f64, u32, i64, i64, u64)> {
+        let contents = pidfd.read_relative("stat")?;
+        Self::parse_stat_fields(&contents)
+    }
+
+    /// Shared core of `/proc/.../stat` parsing, used for both whole
+    /// processes and individual threads under `task/{tid}/stat` (the
+    /// format is identical either way).
+    fn parse_stat_fields(contents: &str) -> Result<(ProcessStatus, f64, f64, u32, i64, i64, u64)> {
+        let close_paren = contents.rfind(')')
+            .ok_or_else(|| anyhow!("no closing paren for comm field"))?;
+        // Field 3 (state) onward; fields[i] here is stat field (i + 3).
+        let fields: Vec<&str> = contents[close_paren + 1..].split_whitespace().collect();
+
+        let state_ch = fields.first()
+            .and_then(|s| s.chars().next())
+            .ok_or_else(|| anyhow!("missing state field"))?;
+        let status = ProcessStatus::from(state_ch);
+
+        let clk_tck = clock_ticks_per_sec();
+        let utime_ticks: u64 = fields.get(11).and_then(|s| s.parse().ok()).unwrap_or(0);
+        let stime_ticks: u64 = fields.get(12).and_then(|s| s.parse().ok()).unwrap_or(0);
+        let priority: i64 = fields.get(15).and_then(|s| s.parse().ok()).unwrap_or(0);
+        let nice: i64 = fields.get(16).and_then(|s| s.parse().ok()).unwrap_or(0);
+        let num_threads: u32 = fields.get(17).and_then(|s| s.parse().ok()).unwrap_or(0);
+        let starttime: u64 = fields.get(19).and_then(|s| s.parse().ok()).unwrap_or(0);
+
+        Ok((
+            status,
+            utime_ticks as f64 / clk_tck,
+            stime_ticks as f64 / clk_tck,
+            num_threads,
+            priority,
+            nice,
+            starttime,
+        ))
+    }
+
+    /// Enumerates `/proc/{pid}/task/` and gathers a `ThreadStats` for each
+    /// live thread. A thread that exits mid-enumeration is simply skipped
+    /// rather than failing the whole batch, since the set of threads is
+    /// inherently a moving target for a running process.
+    pub fn gather_threads(pidfd: &PidFd) -> Result<Vec<ThreadStats>> {
+        let mut task_dir = match pidfd.open_dir_relative("task") {
+            Ok(d) => d,
+            Err(_) => return Ok(Vec::new()),
+        };
+
+        let mut threads = Vec::new();
+        for entry in task_dir.iter() {
+            let entry = match entry {
+                Ok(e) => e,
+                Err(_) => continue,
+            };
+            let name = entry.file_name();
+            let tid: u32 = match name.to_string_lossy().parse() {
+                Ok(t) => t,
+                Err(_) => continue, // "." / ".."
+            };
+
+            let task_fd = match pidfd.open_task(tid) {
+                Ok(f) => f,
+                Err(_) => continue,
+            };
+
+            if let Ok(thread) = Self::gather_thread(&task_fd) {
+                threads.push(thread);
+            }
+        }
+
+        Ok(threads)
+    }
+
+    fn gather_thread(task_fd: &PidFd) -> Result<ThreadStats> {
+        let tid = task_fd.pid();
+        let name = Self::read_name(task_fd)?;
+        let stat_contents = task_fd.read_relative("stat")?;
+        let (status, utime_secs, stime_secs, _num_threads, _priority, _nice, _starttime) =
+            Self::parse_stat_fields(&stat_contents)?;
+
+        let (ppid, flags) = parse_stat_ppid_and_flags(&stat_contents).unwrap_or((0, 0));
+        let cmdline_empty = task_fd.read_relative("cmdline")
+            .map(|c| c.trim().is_empty())
+            .unwrap_or(true);
+        let is_kernel_thread =
+            (flags & PF_KTHREAD != 0) || (cmdline_empty && ppid == KTHREADD_PID);
+
+        Ok(ThreadStats {
+            tid,
+            name,
+            status,
+            utime_secs,
+            stime_secs,
+            is_kernel_thread,
+        })
+    }
+
+    /// Resolves and reads this process's cgroup v2 controller ceilings.
+    /// Returns `Ok(None)` (rather than an error) whenever cgroup v2 isn't
+    /// in play -- a pure cgroup v1 host, a sandbox without
+    /// `/sys/fs/cgroup`, or a missing controller directory -- so callers
+    /// can degrade to rlimits-only.
+    pub fn parse_cgroup_limits(pidfd: &PidFd) -> Result<Option<CgroupLimits>> {
+        let contents = match pidfd.read_relative("cgroup") {
+            Ok(c) => c,
+            Err(_) => return Ok(None),
+        };
+
+        // The v2 line has the form `0::/path/to/cgroup`, with an empty
+        // controller list in the middle field.
+        let cgroup_path = contents.lines().find_map(|l| {
+            let mut parts = l.splitn(3, ':');
+            let hierarchy_id = parts.next()?;
+            let controllers = parts.next()?;
+            let path = parts.next()?;
+            (hierarchy_id == "0" && controllers.is_empty()).then(|| path.to_string())
+        });
+
+        let cgroup_path = match cgroup_path {
+            Some(p) => p,
+            None => return Ok(None),
+        };
+
+        let controller_dir = PathBuf::from("/sys/fs/cgroup").join(cgroup_path.trim_start_matches('/'));
+        if !controller_dir.is_dir() {
+            return Ok(None);
+        }
+
+        Ok(Some(CgroupLimits {
+            mem_current: read_cgroup_u64(&controller_dir, "memory.current").unwrap_or(0),
+            mem_max: read_cgroup_limit(&controller_dir, "memory.max").unwrap_or(u64::MAX),
+            pids_current: read_cgroup_u64(&controller_dir, "pids.current").unwrap_or(0),
+            pids_max: read_cgroup_limit(&controller_dir, "pids.max").unwrap_or(u64::MAX),
+            swap_current: read_cgroup_u64(&controller_dir, "memory.swap.current").unwrap_or(0),
+            swap_max: read_cgroup_limit(&controller_dir, "memory.swap.max").unwrap_or(u64::MAX),
+        }))
+    }
+
+    /// The ceiling this process will actually hit first on thread/process
+    /// count: the tighter of its rlimit (`Max processes`) and its cgroup
+    /// v2 `pids.max`. Falls back to the rlimit alone when there's no
+    /// cgroup v2 data (e.g. a cgroup v1-only host).
+    pub fn effective_thread_limit(&self) -> Option<u64> {
+        let rlimit = self.threads_hard_limit?;
+        match &self.cgroup_limits {
+            Some(cgroup) => Some(tighter_limit(rlimit, cgroup.pids_max)),
+            None => Some(rlimit),
+        }
+    }
+
+    /// The tighter of the rlimit `Max address space` and cgroup v2
+    /// `memory.max`.
+    ///
+    /// These are *not* the same quantity: `Max address space` (`RLIMIT_AS`)
+    /// caps virtual address space (`VmSize`), while `memory.max` caps
+    /// physical/resident usage (closer to `VmRSS`). A process with a large
+    /// virtual reservation (e.g. a big `mmap`) that never touches most of
+    /// it can have a low `Max address space` headroom while nowhere near
+    /// its cgroup ceiling, or vice versa. Treat this as "the lower of two
+    /// different ceilings that both happen to be called a memory limit",
+    /// not as a single OOM-distance predictor.
+    pub fn effective_mem_limit(&self) -> Option<u64> {
+        let rlimit = self.mem_hard_limit?;
+        match &self.cgroup_limits {
+            Some(cgroup) => Some(tighter_limit(rlimit, cgroup.mem_max)),
+            None => Some(rlimit),
+        }
+    }
+
+    /// Reads `/proc/{pid}/ns/*` and resolves each entry's namespace inode.
+    /// Falls back to an all-`None` `NamespaceIds` when `ns/` can't be
+    /// opened at all (e.g. insufficient privilege against another user's
+    /// process), and leaves individual fields `None` when a single entry
+    /// can't be read.
+    pub fn parse_namespaces(pidfd: &PidFd) -> Result<NamespaceIds> {
+        let ns_dir = match pidfd.open_dir_relative("ns") {
+            Ok(d) => d,
+            Err(_) => return Ok(NamespaceIds::default()),
+        };
+        let dir_fd = ns_dir.as_raw_fd();
+
+        Ok(NamespaceIds {
+            pid: read_ns_inode(dir_fd, "pid"),
+            mnt: read_ns_inode(dir_fd, "mnt"),
+            net: read_ns_inode(dir_fd, "net"),
+            user: read_ns_inode(dir_fd, "user"),
+            cgroup: read_ns_inode(dir_fd, "cgroup"),
+            uts: read_ns_inode(dir_fd, "uts"),
+            ipc: read_ns_inode(dir_fd, "ipc"),
+        })
+    }
 }
 
 fn main() -> Result<()> {
@@ -298,12 +838,14 @@ fn contruct_ptree_for_ppid(ppid: u32) -> Result<Vec<ProcStats>> {
 }
 
 fn inspect_pid_tree(root_pid: u32) -> Result<()> {
-    // Recursively traverse children
+    // Recursively traverse children, then group_by_namespace() before
+    // printing so containerized subtrees cluster apart from the host
     unimplemented!()
 }
 
 fn inspect_pid_list(pids: &[u32]) -> Result<()> {
-    // Loop through list, analyze usage vs. limits
+    // Loop through list, analyze usage vs. limits, then group_by_namespace()
+    // before printing so containerized subtrees cluster apart from the host
     unimplemented!()
 }
 
@@ -320,6 +862,90 @@ fn parse_limit(s: &str) -> u64 {
     }
 }
 
+fn read_cgroup_u64(controller_dir: &std::path::Path, file: &str) -> Option<u64> {
+    fs::read_to_string(controller_dir.join(file))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Like `read_cgroup_u64`, but treats the literal value `"max"` (cgroup
+/// v2's spelling of "no ceiling") as `u64::MAX`, mirroring `parse_limit`'s
+/// handling of rlimit's `"unlimited"`.
+fn read_cgroup_limit(controller_dir: &std::path::Path, file: &str) -> Option<u64> {
+    let raw = fs::read_to_string(controller_dir.join(file)).ok()?;
+    parse_cgroup_limit_value(&raw)
+}
+
+/// `"max"` is cgroup v2's spelling of "no ceiling"; everything else is a
+/// plain integer. Split out from `read_cgroup_limit` so the parsing can be
+/// unit tested without a real `/sys/fs/cgroup` file.
+fn parse_cgroup_limit_value(raw: &str) -> Option<u64> {
+    let raw = raw.trim();
+    if raw == "max" {
+        Some(u64::MAX)
+    } else {
+        raw.parse().ok()
+    }
+}
+
+/// The ceiling a process will actually hit first: whichever of the rlimit
+/// and the cgroup v2 controller limit is tighter.
+fn tighter_limit(rlimit: u64, cgroup: u64) -> u64 {
+    rlimit.min(cgroup)
+}
+
+/// Pulls just `ppid` (field 4) and `flags` (field 9) out of a `stat` line,
+/// for kernel-thread detection. See `ProcStats::parse_stat` for the
+/// comm-splitting rationale shared by both parsers.
+fn parse_stat_ppid_and_flags(contents: &str) -> Option<(u32, u64)> {
+    let close_paren = contents.rfind(')')?;
+    let fields: Vec<&str> = contents[close_paren + 1..].split_whitespace().collect();
+    let ppid: u32 = fields.get(1)?.parse().ok()?;
+    let flags: u64 = fields.get(6)?.parse().ok()?;
+    Some((ppid, flags))
+}
+
+/// Reads `ns/{name}` (e.g. `"net"`, `"pid"`) and pulls the inode number out
+/// of its `readlink` target, which looks like `net:[4026531840]`.
+fn read_ns_inode(ns_dir_fd: RawFd, name: &str) -> Option<u64> {
+    let target = nix::fcntl::readlinkat(ns_dir_fd, name).ok()?;
+    parse_ns_inode(&target.to_string_lossy())
+}
+
+/// Pulls the inode number out of a namespace `readlink` target like
+/// `net:[4026531840]`. Split out from `read_ns_inode` so the parsing can
+/// be unit tested without a real `/proc/{pid}/ns` entry.
+fn parse_ns_inode(target: &str) -> Option<u64> {
+    let start = target.find('[')?;
+    let end = target.find(']')?;
+    target[start + 1..end].parse().ok()
+}
+
+/// Groups processes by (PID-namespace, net-namespace) inode pair, so
+/// processes sharing both belong to the same container and land in one
+/// group, separate from the host's init-namespace processes.
+/// `inspect_pid_list` and `inspect_pid_tree` are placeholders and do not
+/// call this function, so their output is not grouped.
+fn group_by_namespace(stats: &[ProcStats]) -> HashMap<(Option<u64>, Option<u64>), Vec<&ProcStats>> {
+    let mut groups: HashMap<(Option<u64>, Option<u64>), Vec<&ProcStats>> = HashMap::new();
+    for stat in stats {
+        groups
+            .entry((stat.namespaces.pid, stat.namespaces.net))
+            .or_default()
+            .push(stat);
+    }
+    groups
+}
+
+fn clock_ticks_per_sec() -> f64 {
+    match nix::unistd::sysconf(nix::unistd::SysconfVar::CLK_TCK) {
+        Ok(Some(ticks)) if ticks > 0 => ticks as f64,
+        _ => 100.0, // USER_HZ defaults to 100 on virtually every Linux config
+    }
+}
+
 fn extract_kb(line: &str) -> u64 {
     match line.split_whitespace().nth(1) {
         Some(val) => match val.parse::<u64>() {
@@ -328,4 +954,89 @@ fn extract_kb(line: &str) -> u64 {
         },
         None => 0,
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_stat_fields_splits_comm_on_last_close_paren() {
+        // comm itself (`a ) b`) contains both a space and a `)`; only the
+        // *last* `)` in the line is the real end of the comm field.
+        let line = "1 (a ) b) R 0 0 0 0 -1 0 0 0 0 0 1500 300 0 0 20 0 4 0 98765 0";
+        let (status, utime_secs, stime_secs, num_threads, priority, nice, starttime) =
+            ProcStats::parse_stat_fields(line).unwrap();
+
+        assert_eq!(status, ProcessStatus::Run);
+        assert_eq!(num_threads, 4);
+        assert_eq!(priority, 20);
+        assert_eq!(nice, 0);
+        assert_eq!(starttime, 98765);
+        // utime/stime ticks are 1500/300; the ratio is independent of
+        // whatever CLK_TCK the test machine reports.
+        assert!((utime_secs / stime_secs - 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn parse_stat_fields_maps_all_known_state_chars() {
+        for (ch, expected) in [
+            ('R', ProcessStatus::Run),
+            ('S', ProcessStatus::Sleep),
+            ('D', ProcessStatus::UninterruptibleDiskSleep),
+            ('I', ProcessStatus::Idle),
+            ('Z', ProcessStatus::Zombie),
+            ('T', ProcessStatus::Stop),
+            ('t', ProcessStatus::Tracing),
+            ('X', ProcessStatus::Dead),
+            ('x', ProcessStatus::Dead),
+            ('W', ProcessStatus::Paging),
+            ('K', ProcessStatus::Wakekill),
+            ('P', ProcessStatus::Parked),
+        ] {
+            let line = format!("1 (x) {ch} 0 0 0 0 -1 0 0 0 0 0 0 0 0 0 20 0 1 0 0 0");
+            let (status, ..) = ProcStats::parse_stat_fields(&line).unwrap();
+            assert_eq!(status, expected);
+        }
+    }
+
+    #[test]
+    fn parse_stat_fields_rejects_missing_close_paren() {
+        assert!(ProcStats::parse_stat_fields("1 (unterminated R 0").is_err());
+    }
+
+    #[test]
+    fn parse_ns_inode_extracts_bracketed_number() {
+        assert_eq!(parse_ns_inode("net:[4026531840]"), Some(4026531840));
+        assert_eq!(parse_ns_inode("mnt:[4026531841]"), Some(4026531841));
+        assert_eq!(parse_ns_inode("garbage"), None);
+    }
+
+    #[test]
+    fn parse_cgroup_limit_value_treats_max_as_unlimited() {
+        assert_eq!(parse_cgroup_limit_value("max\n"), Some(u64::MAX));
+        assert_eq!(parse_cgroup_limit_value("12345\n"), Some(12345));
+        assert_eq!(parse_cgroup_limit_value("not a number"), None);
+    }
+
+    #[test]
+    fn classify_fd_target_recognizes_synthetic_targets() {
+        assert_eq!(classify_fd_target("socket:[12345]"), Some(FdCategory::Socket));
+        assert_eq!(classify_fd_target("pipe:[6789]"), Some(FdCategory::Pipe));
+        assert_eq!(
+            classify_fd_target("anon_inode:[eventfd]"),
+            Some(FdCategory::AnonInode)
+        );
+        assert_eq!(classify_fd_target("/var/log/app.log"), None);
+    }
+
+    #[test]
+    fn classify_fd_mode_masks_s_ifmt_before_comparing() {
+        assert_eq!(classify_fd_mode(SFlag::S_IFDIR.bits()), FdCategory::Directory);
+        assert_eq!(classify_fd_mode(SFlag::S_IFCHR.bits()), FdCategory::Device);
+        // S_IFBLK previously matched the S_IFDIR branch because S_IFBLK's
+        // bits are a superset of S_IFDIR's without masking to S_IFMT first.
+        assert_eq!(classify_fd_mode(SFlag::S_IFBLK.bits()), FdCategory::Device);
+        assert_eq!(classify_fd_mode(SFlag::S_IFREG.bits()), FdCategory::Regular);
+    }
 }
\ No newline at end of file